@@ -0,0 +1,190 @@
+//! KMAC128 and KMAC256 as defined in [NIST SP 800-185].
+//!
+//! `Hmac` is generic over any hash function and thus also accepts SHA-3, but
+//! KMAC is the keyed construction recommended for Keccak-based hashes. Both
+//! types implement the `Mac` trait with an empty customization string, use
+//! `new_customized` to provide one.
+//!
+//! [NIST SP 800-185]: https://doi.org/10.6028/NIST.SP.800-185
+use core::fmt;
+use crypto_mac::generic_array::typenum::{Unsigned, U32, U64};
+use crypto_mac::generic_array::GenericArray;
+use crypto_mac::{InvalidKeyLength, Mac, MacError, MacResult};
+use fixed_size_code;
+use keccak;
+
+/// Function name string `N` used by KMAC
+const FUNCTION_NAME: &[u8] = b"KMAC";
+
+/// Keccak sponge with the cSHAKE padding, absorbing input directly into the
+/// state lanes.
+#[derive(Clone)]
+struct Sponge {
+    state: [u64; 25],
+    rate: usize,
+    pos: usize,
+}
+
+impl Sponge {
+    /// Create a cSHAKE instance with the KMAC function name and the given
+    /// customization string.
+    fn new(rate: usize, customization: &[u8]) -> Sponge {
+        let mut sponge = Sponge {
+            state: [0u64; 25],
+            rate,
+            pos: 0,
+        };
+        sponge.left_encode(rate as u64);
+        sponge.encode_string(FUNCTION_NAME);
+        sponge.encode_string(customization);
+        sponge.pad_block();
+        sponge
+    }
+
+    fn absorb(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.state[self.pos / 8] ^= (byte as u64) << (8 * (self.pos % 8));
+            self.pos += 1;
+            if self.pos == self.rate {
+                keccak::f1600(&mut self.state);
+                self.pos = 0;
+            }
+        }
+    }
+
+    /// `left_encode` from SP 800-185 section 2.3.1
+    fn left_encode(&mut self, x: u64) {
+        let (buf, n) = encode_int(x);
+        self.absorb(&[n as u8]);
+        self.absorb(&buf[8 - n..]);
+    }
+
+    /// `right_encode` from SP 800-185 section 2.3.1
+    fn right_encode(&mut self, x: u64) {
+        let (buf, n) = encode_int(x);
+        self.absorb(&buf[8 - n..]);
+        self.absorb(&[n as u8]);
+    }
+
+    /// `encode_string` from SP 800-185 section 2.3.2
+    fn encode_string(&mut self, s: &[u8]) {
+        self.left_encode(8 * s.len() as u64);
+        self.absorb(s);
+    }
+
+    /// Zero padding to the rate as done by `bytepad`. Absorbing zeros leaves
+    /// the state unchanged, so only the pending permutation is applied.
+    fn pad_block(&mut self) {
+        if self.pos != 0 {
+            keccak::f1600(&mut self.state);
+            self.pos = 0;
+        }
+    }
+
+    /// Apply cSHAKE padding and squeeze `out.len()` bytes.
+    fn finalize(mut self, out: &mut [u8]) {
+        let last = self.rate - 1;
+        self.state[self.pos / 8] ^= 0x04 << (8 * (self.pos % 8));
+        self.state[last / 8] ^= 0x80 << (8 * (last % 8));
+        keccak::f1600(&mut self.state);
+
+        for (i, byte) in out.iter_mut().enumerate() {
+            let pos = i % self.rate;
+            if i != 0 && pos == 0 {
+                keccak::f1600(&mut self.state);
+            }
+            *byte = (self.state[pos / 8] >> (8 * (pos % 8))) as u8;
+        }
+    }
+}
+
+/// Big-endian encoding of `x` and the minimal number of bytes (at least one)
+/// needed to represent it.
+fn encode_int(x: u64) -> ([u8; 8], usize) {
+    let mut buf = [0u8; 8];
+    for (i, byte) in buf.iter_mut().enumerate() {
+        *byte = (x >> (8 * (7 - i))) as u8;
+    }
+    let n = 8 - buf.iter().take(7).take_while(|&&b| b == 0).count();
+    (buf, n)
+}
+
+macro_rules! impl_kmac {
+    ($name:ident, $rate:expr, $key_size:ty, $output_size:ty, $doc:expr) => {
+        #[doc=$doc]
+        #[derive(Clone)]
+        pub struct $name {
+            sponge: Sponge,
+            initial: Sponge,
+        }
+
+        impl $name {
+            /// Create new instance from key of any size and the
+            /// customization string `S`.
+            pub fn new_customized(key: &[u8], customization: &[u8]) -> Self {
+                let mut sponge = Sponge::new($rate, customization);
+                sponge.left_encode($rate as u64);
+                sponge.encode_string(key);
+                sponge.pad_block();
+                $name {
+                    sponge: sponge.clone(),
+                    initial: sponge,
+                }
+            }
+        }
+
+        impl Mac for $name {
+            type OutputSize = $output_size;
+            type KeySize = $key_size;
+
+            fn new(key: &GenericArray<u8, Self::KeySize>) -> Self {
+                Self::new_customized(key.as_slice(), &[])
+            }
+
+            #[inline]
+            fn new_varkey(key: &[u8]) -> Result<Self, InvalidKeyLength> {
+                Ok(Self::new_customized(key, &[]))
+            }
+
+            #[inline]
+            fn input(&mut self, data: &[u8]) {
+                self.sponge.absorb(data);
+            }
+
+            fn result(self) -> MacResult<Self::OutputSize> {
+                let mut sponge = self.sponge;
+                sponge.right_encode(8 * Self::OutputSize::to_u64());
+                let mut code = GenericArray::default();
+                sponge.finalize(&mut code);
+                MacResult::new(code)
+            }
+
+            #[inline]
+            fn reset(&mut self) {
+                self.sponge = self.initial.clone();
+            }
+
+            /// Same as for `Hmac`, a code of the wrong length is compared in
+            /// constant time before it is rejected.
+            fn verify(self, code: &[u8]) -> Result<(), MacError> {
+                let code_eq = self.result() == fixed_size_code(code);
+                let len_eq = code.len() == Self::OutputSize::to_usize();
+                if code_eq & len_eq {
+                    Ok(())
+                } else {
+                    Err(MacError)
+                }
+            }
+        }
+
+        // The sponge state is derived from the key, so it is never printed.
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(concat!(stringify!($name), " { ... }"))
+            }
+        }
+    };
+}
+
+impl_kmac!(Kmac128, 168, U32, U32, "KMAC128 with 256-bit output");
+impl_kmac!(Kmac256, 136, U64, U64, "KMAC256 with 512-bit output");
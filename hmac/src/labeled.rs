@@ -0,0 +1,97 @@
+use core::fmt;
+use crypto_mac::{InvalidKeyLength, Mac, MacError, MacResult};
+use digest::generic_array::ArrayLength;
+use digest::{BlockInput, FixedOutput, Input, Reset};
+use Hmac;
+
+/// Domain separated HMAC computing `HMAC(key, label || message)`.
+///
+/// The label is absorbed once on construction right after the key pad, and
+/// `reset` restores the state after the label, so every message processed by
+/// an instance is bound to the same label. Output is identical to `Hmac` with
+/// the label prepended to each message.
+///
+/// The label is not length-framed, so the set of labels used with one key
+/// must be prefix-free (e.g. fixed-length or NUL-terminated labels). Otherwise
+/// contexts collide: label `b"ab"` with message `b"c"` produces the same tag
+/// as label `b"a"` with message `b"bc"`.
+pub struct LabeledHmac<D>
+where
+    D: Input + BlockInput + FixedOutput + Reset + Default + Clone,
+    D::BlockSize: ArrayLength<u8>,
+{
+    hmac: Hmac<D>,
+}
+
+impl<D> LabeledHmac<D>
+where
+    D: Input + BlockInput + FixedOutput + Reset + Default + Clone,
+    D::BlockSize: ArrayLength<u8>,
+{
+    /// Create new instance for `key` and the domain separation `label`.
+    pub fn new(key: &[u8], label: &[u8]) -> Result<Self, InvalidKeyLength> {
+        Ok(LabeledHmac {
+            hmac: Hmac::with_prefix(key, label)?,
+        })
+    }
+
+    /// Process input data.
+    #[inline]
+    pub fn input(&mut self, data: &[u8]) {
+        self.hmac.input(data);
+    }
+
+    /// Process input messages in a chained manner
+    #[inline]
+    pub fn chain(mut self, data: &[u8]) -> Self {
+        self.hmac.input(data);
+        self
+    }
+
+    /// Reset instance to the state right after the label.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.hmac.reset();
+    }
+
+    /// Obtain the result and consume the instance.
+    #[inline]
+    pub fn result(self) -> MacResult<D::OutputSize> {
+        self.hmac.result()
+    }
+
+    /// Obtain the result and reset the instance to the state right after the
+    /// label.
+    #[inline]
+    pub fn result_reset(&mut self) -> MacResult<D::OutputSize> {
+        self.hmac.result_reset()
+    }
+
+    /// Check if code is correct for the processed input.
+    #[inline]
+    pub fn verify(self, code: &[u8]) -> Result<(), MacError> {
+        self.hmac.verify(code)
+    }
+}
+
+impl<D> Clone for LabeledHmac<D>
+where
+    D: Input + BlockInput + FixedOutput + Reset + Default + Clone,
+    D::BlockSize: ArrayLength<u8>,
+{
+    fn clone(&self) -> LabeledHmac<D> {
+        LabeledHmac {
+            hmac: self.hmac.clone(),
+        }
+    }
+}
+
+impl<D> fmt::Debug for LabeledHmac<D>
+where
+    D: Input + BlockInput + FixedOutput + Reset + Default + Clone,
+    D::BlockSize: ArrayLength<u8>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("LabeledHmac { ... }")
+    }
+}
@@ -55,23 +55,77 @@
 //! # }
 //! ```
 //!
+//! `verify` takes the expected code as a plain byte slice, so a tag received
+//! e.g. in a network frame can be passed directly. A code of the wrong length
+//! results in the same `MacError` as a code with the wrong value.
+//!
+//! When the message and its tag are received together, a single call to
+//! `verify_mac` creates the instance, processes the message and checks the tag:
+//!
+//! ```rust
+//! # extern crate hmac;
+//! # extern crate sha2;
+//! # use sha2::Sha256;
+//! # use hmac::Hmac;
+//! # fn main() {
+//! # type HmacSha256 = Hmac<Sha256>;
+//! let key = b"my secret and secure key";
+//! # let tag = HmacSha256::mac(key, b"input message").unwrap().code();
+//! HmacSha256::verify_mac(key, b"input message", &tag).unwrap();
+//! # }
+//! ```
+//!
+//! # Constant time
+//! Comparisons of `MacResult` values, `verify` and `verify_ct_choice` run in
+//! time independent of the contents of the compared codes, and `verify` of
+//! `Hmac`, `LabeledHmac` and the KMAC types does not return early on a code
+//! of the wrong length. Timing of the MAC computation itself depends only on
+//! the length of the key and input, as long as the same holds for the
+//! underlying hash function. The `ct` feature enables a statistical timing
+//! test of `verify` in the test suite.
+//!
 //! # Block and input sizes
 //! Usually it is assumed that block size is larger than output size, due to the
 //! generic nature of the implementation this edge case must be handled as well
 //! to remove potential panic scenario. This is done by truncating hash output
 //! to the hash block size if needed.
+//!
+//! # KMAC
+//! For Keccak-based hashes prefer KMAC over HMAC-SHA3. KMAC128 and KMAC256 are
+//! available in the `kmac` module after enabling the `kmac` feature.
+//!
+//! # Type aliases
+//! The `primitives` feature enables the `primitives` module with aliases such
+//! as `HmacSha256`, wired to compatible versions of the `sha-1` and `sha2`
+//! crates.
 #![no_std]
 #![doc(html_logo_url = "https://raw.githubusercontent.com/RustCrypto/meta/master/logo_small.png")]
 pub extern crate crypto_mac;
 pub extern crate digest;
+#[cfg(feature = "kmac")]
+extern crate keccak;
+#[cfg(feature = "subtle")]
+extern crate subtle;
+
+#[cfg(feature = "kmac")]
+pub mod kmac;
+mod labeled;
+#[cfg(feature = "primitives")]
+pub mod primitives;
+pub mod tls_prf;
 
 use core::cmp::min;
 use core::fmt;
+use core::mem;
 pub use crypto_mac::Mac;
-use crypto_mac::{InvalidKeyLength, MacResult};
+use crypto_mac::{InvalidKeyLength, MacError, MacResult};
 use digest::generic_array::sequence::GenericSequence;
+use digest::generic_array::typenum::Unsigned;
 pub use digest::generic_array::{ArrayLength, GenericArray};
-use digest::{BlockInput, FixedOutput, Input, Reset};
+use digest::{BlockInput, FixedOutput, Input, InvalidOutputSize, Reset};
+pub use labeled::LabeledHmac;
+#[cfg(feature = "subtle")]
+use subtle::{Choice, ConstantTimeEq};
 
 const IPAD: u8 = 0x36;
 const OPAD: u8 = 0x5C;
@@ -83,7 +137,7 @@ where
     D::BlockSize: ArrayLength<u8>,
 {
     digest: D,
-    i_key_pad: GenericArray<u8, D::BlockSize>,
+    ipad_digest: D,
     opad_digest: D,
 }
 
@@ -95,23 +149,171 @@ where
     fn clone(&self) -> Hmac<D> {
         Hmac {
             digest: self.digest.clone(),
-            i_key_pad: self.i_key_pad.clone(),
+            ipad_digest: self.ipad_digest.clone(),
             opad_digest: self.opad_digest.clone(),
         }
     }
 }
 
+impl<D> Hmac<D>
+where
+    D: Input + BlockInput + FixedOutput + Reset + Default + Clone,
+    D::BlockSize: ArrayLength<u8>,
+{
+    /// Block size of the underlying hash function in bytes, i.e.
+    /// `D::BlockSize` as a plain `usize`.
+    pub const BLOCK_SIZE: usize = <D::BlockSize as Unsigned>::USIZE;
+
+    /// Same as `BLOCK_SIZE`, for use where a function is more convenient.
+    #[inline]
+    pub fn block_size() -> usize {
+        Self::BLOCK_SIZE
+    }
+
+    /// Create new instance whose inner digest has absorbed `prefix` right
+    /// after the key, i.e. every message `m` is authenticated as
+    /// `prefix || m`.
+    ///
+    /// Note that `reset` (and `result_reset`) restore the state after the
+    /// prefix, so the prefix is effectively re-absorbed for every message
+    /// without being processed again.
+    pub fn with_prefix(key: &[u8], prefix: &[u8]) -> Result<Self, InvalidKeyLength> {
+        let mut hmac = <Self as Mac>::new_varkey(key)?;
+        hmac.ipad_digest.input(prefix);
+        hmac.digest = hmac.ipad_digest.clone();
+        Ok(hmac)
+    }
+
+    /// Create new instance from a key which was already hashed with `D`,
+    /// skipping the hashing of keys longer than the block size.
+    ///
+    /// To be interoperable `hashed_key` must be exactly `D(key)`, i.e. the
+    /// hash which `new_varkey` would compute for the original long key.
+    pub fn new_prehashed_key(hashed_key: &GenericArray<u8, D::OutputSize>) -> Self {
+        // `n` is calculated at compile time and will equal
+        // D::OutputSize. This is used to ensure panic-free code
+        let n = min(hashed_key.len(), D::BlockSize::to_usize());
+        Self::from_block_key(&hashed_key[..n])
+    }
+
+    /// Create new instance from a key which is not longer than the block size.
+    fn from_block_key(key: &[u8]) -> Self {
+        let mut ipad = GenericArray::<u8, D::BlockSize>::generate(|_| IPAD);
+        let mut opad = GenericArray::<u8, D::BlockSize>::generate(|_| OPAD);
+        debug_assert!(key.len() <= ipad.len());
+
+        for (k_idx, k_itm) in key.iter().enumerate() {
+            ipad[k_idx] ^= *k_itm;
+            opad[k_idx] ^= *k_itm;
+        }
+
+        let mut ipad_digest = D::default();
+        ipad_digest.input(&ipad);
+        let mut opad_digest = D::default();
+        opad_digest.input(&opad);
+
+        Hmac {
+            digest: ipad_digest.clone(),
+            ipad_digest,
+            opad_digest,
+        }
+    }
+
+    /// Compute the MAC of a single message `msg` under `key`.
+    pub fn mac(key: &[u8], msg: &[u8]) -> Result<MacResult<D::OutputSize>, InvalidKeyLength> {
+        let mut hmac = <Self as Mac>::new_varkey(key)?;
+        hmac.input(msg);
+        Ok(hmac.result())
+    }
+
+    /// Check if `tag` is the correct code of a single message `msg` under
+    /// `key`.
+    pub fn verify_mac(key: &[u8], msg: &[u8], tag: &[u8]) -> Result<(), MacError> {
+        let mut hmac = <Self as Mac>::new_varkey(key).map_err(|_| MacError)?;
+        hmac.input(msg);
+        hmac.verify(tag)
+    }
+
+    /// Write the result into `out` and reset the instance, allowing to reuse
+    /// both the output buffer and the `Hmac` instance in verification loops.
+    #[inline]
+    pub fn finalize_into_reset(&mut self, out: &mut GenericArray<u8, D::OutputSize>) {
+        *out = self.result_reset().code();
+    }
+
+    /// Perform a symmetric-key ratchet step as used by ratcheting protocols,
+    /// returning `(chain_key, message_key)` where
+    /// `chain_key = HMAC(key, 0x01)` and `message_key = HMAC(key, 0x02)`.
+    ///
+    /// Note that the intermediate keyed digest states are dropped without
+    /// being zeroized: digest 0.8 hash states provide no way to wipe them
+    /// generically.
+    pub fn ratchet(
+        key: &[u8],
+    ) -> (
+        GenericArray<u8, D::OutputSize>,
+        GenericArray<u8, D::OutputSize>,
+    ) {
+        let hmac = <Self as Mac>::new_varkey(key).unwrap();
+        let chain_key = hmac.clone().chain(&[0x01]).result().code();
+        let message_key = hmac.chain(&[0x02]).result().code();
+        (chain_key, message_key)
+    }
+
+    /// Check if `tag` is correct for the processed input, returning the result
+    /// of the constant time comparison as a `Choice` so that it can be
+    /// combined with other checks before branching once.
+    ///
+    /// As with `verify`, a `tag` of the wrong length is still compared in full
+    /// and results in a `Choice` of `0`.
+    #[cfg(feature = "subtle")]
+    pub fn verify_ct_choice(self, tag: &[u8]) -> Choice {
+        let len_eq = Choice::from((tag.len() == D::OutputSize::to_usize()) as u8);
+        self.result().ct_eq(&fixed_size_code(tag)) & len_eq
+    }
+
+    /// Process input messages in a chained manner
+    #[inline]
+    pub fn chain(mut self, data: &[u8]) -> Self {
+        self.digest.input(data);
+        self
+    }
+
+    /// Process input given as several discontiguous chunks, e.g. scatter-gather
+    /// I/O buffers, as if they were concatenated.
+    #[inline]
+    pub fn input_chunks<'a, I>(&mut self, chunks: I)
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        for chunk in chunks {
+            self.digest.input(chunk);
+        }
+    }
+
+    /// Finalize the MAC and write its first `nonce.len()` bytes into `nonce`,
+    /// for deterministic nonce derivation such as `HMAC(key, ad || msg)`.
+    ///
+    /// Returns `InvalidOutputSize` if `nonce` is empty or longer than the
+    /// output size of `D`.
+    pub fn derive_nonce(self, nonce: &mut [u8]) -> Result<(), InvalidOutputSize> {
+        if nonce.is_empty() || nonce.len() > D::OutputSize::to_usize() {
+            return Err(InvalidOutputSize);
+        }
+        let code = self.result().code();
+        nonce.copy_from_slice(&code[..nonce.len()]);
+        Ok(())
+    }
+}
+
+// All fields are derived from the key, so none of them are printed.
 impl<D> fmt::Debug for Hmac<D>
 where
-    D: Input + BlockInput + FixedOutput + Reset + Default + Clone + fmt::Debug,
+    D: Input + BlockInput + FixedOutput + Reset + Default + Clone,
     D::BlockSize: ArrayLength<u8>,
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("Hmac")
-            .field("digest", &self.digest)
-            .field("i_key_pad", &self.i_key_pad)
-            .field("opad_digest", &self.opad_digest)
-            .finish()
+        f.write_str("Hmac { ... }")
     }
 }
 
@@ -130,41 +332,17 @@ where
 
     #[inline]
     fn new_varkey(key: &[u8]) -> Result<Self, InvalidKeyLength> {
-        let mut hmac = Self {
-            digest: Default::default(),
-            i_key_pad: GenericArray::generate(|_| IPAD),
-            opad_digest: Default::default(),
-        };
-
-        let mut opad = GenericArray::<u8, D::BlockSize>::generate(|_| OPAD);
-        debug_assert!(hmac.i_key_pad.len() == opad.len());
-
         // The key that Hmac processes must be the same as the block size of the
         // underlying Digest. If the provided key is smaller than that, we just
         // pad it with zeros. If its larger, we hash it and then pad it with
         // zeros.
-        if key.len() <= hmac.i_key_pad.len() {
-            for (k_idx, k_itm) in key.iter().enumerate() {
-                hmac.i_key_pad[k_idx] ^= *k_itm;
-                opad[k_idx] ^= *k_itm;
-            }
+        if key.len() <= D::BlockSize::to_usize() {
+            Ok(Self::from_block_key(key))
         } else {
             let mut digest = D::default();
             digest.input(key);
-            let output = digest.fixed_result();
-            // `n` is calculated at compile time and will equal
-            // D::OutputSize. This is used to ensure panic-free code
-            let n = min(output.len(), hmac.i_key_pad.len());
-            for idx in 0..n {
-                hmac.i_key_pad[idx] ^= output[idx];
-                opad[idx] ^= output[idx];
-            }
+            Ok(Self::new_prehashed_key(&digest.fixed_result()))
         }
-
-        hmac.digest.input(&hmac.i_key_pad);
-        hmac.opad_digest.input(&opad);
-
-        Ok(hmac)
     }
 
     #[inline]
@@ -174,15 +352,55 @@ where
 
     #[inline]
     fn result(self) -> MacResult<D::OutputSize> {
-        let mut opad_digest = self.opad_digest.clone();
+        let mut opad_digest = self.opad_digest;
         let hash = self.digest.fixed_result();
         opad_digest.input(&hash);
         MacResult::new(opad_digest.fixed_result())
     }
 
+    /// Obtain the result for the current message and restore the inner
+    /// digest to its keyed state, so the same instance can be reused for the
+    /// next message under the same key without calling `new_varkey` again.
+    ///
+    /// The outer digest has to be cloned since it is kept for the following
+    /// messages, while the inner one is finalized in place.
+    #[inline]
+    fn result_reset(&mut self) -> MacResult<D::OutputSize> {
+        let mut opad_digest = self.opad_digest.clone();
+        let digest = mem::replace(&mut self.digest, self.ipad_digest.clone());
+        let hash = digest.fixed_result();
+        opad_digest.input(&hash);
+        MacResult::new(opad_digest.fixed_result())
+    }
+
     #[inline]
     fn reset(&mut self) {
-        self.digest.reset();
-        self.digest.input(&self.i_key_pad);
+        self.digest = self.ipad_digest.clone();
+    }
+
+    /// Check if code is correct for the processed input.
+    ///
+    /// Unlike the default implementation this does not return early if `code`
+    /// has the wrong length: it is copied into a buffer of the output size,
+    /// which is always compared in constant time before the length check is
+    /// applied.
+    fn verify(self, code: &[u8]) -> Result<(), MacError> {
+        let code_eq = self.result() == fixed_size_code(code);
+        let len_eq = code.len() == D::OutputSize::to_usize();
+        if code_eq & len_eq {
+            Ok(())
+        } else {
+            Err(MacError)
+        }
     }
 }
+
+/// Copy `code` into a buffer of `N` bytes, truncating or padding it with
+/// zeros so that it can be compared without an early return on a length
+/// mismatch.
+fn fixed_size_code<N: ArrayLength<u8>>(code: &[u8]) -> MacResult<N> {
+    let n = min(code.len(), N::to_usize());
+    let mut buf = GenericArray::<u8, N>::default();
+    buf[..n].copy_from_slice(&code[..n]);
+    MacResult::new(buf)
+}
@@ -0,0 +1,16 @@
+//! HMAC type aliases for commonly used hash functions.
+//!
+//! The `sha-1` and `sha2` crates are pulled in with versions compatible with
+//! the `digest` version used by this crate and are re-exported, so there is
+//! no need to pick matching versions of them by hand.
+pub extern crate sha1;
+pub extern crate sha2;
+
+use Hmac;
+
+/// HMAC-SHA1
+pub type HmacSha1 = Hmac<sha1::Sha1>;
+/// HMAC-SHA256
+pub type HmacSha256 = Hmac<sha2::Sha256>;
+/// HMAC-SHA512
+pub type HmacSha512 = Hmac<sha2::Sha512>;
@@ -0,0 +1,65 @@
+//! TLS 1.2 pseudorandom function (PRF) built on top of HMAC.
+//!
+//! See [RFC 5246 section 5](https://tools.ietf.org/html/rfc5246#section-5).
+use crypto_mac::Mac;
+use digest::generic_array::typenum::Unsigned;
+use digest::generic_array::ArrayLength;
+use digest::{BlockInput, FixedOutput, Input, Reset};
+use Hmac;
+
+/// Data expansion function `P_hash(secret, seed)` filling `out` completely.
+///
+/// Output of the final HMAC block is truncated if `out.len()` is not
+/// a multiple of the hash output size.
+pub fn p_hash<D>(secret: &[u8], seed: &[u8], out: &mut [u8])
+where
+    D: Input + BlockInput + FixedOutput + Reset + Default + Clone,
+    D::BlockSize: ArrayLength<u8>,
+{
+    p_hash_parts::<D>(secret, &[seed], out);
+}
+
+/// TLS 1.2 `PRF(secret, label, seed) = P_<hash>(secret, label + seed)`.
+pub fn tls12_prf<D>(secret: &[u8], label: &[u8], seed: &[u8], out: &mut [u8])
+where
+    D: Input + BlockInput + FixedOutput + Reset + Default + Clone,
+    D::BlockSize: ArrayLength<u8>,
+{
+    p_hash_parts::<D>(secret, &[label, seed], out);
+}
+
+/// `P_hash` for a seed given as a concatenation of several parts, which
+/// allows to avoid allocating a buffer for `label + seed`.
+fn p_hash_parts<D>(secret: &[u8], seed: &[&[u8]], out: &mut [u8])
+where
+    D: Input + BlockInput + FixedOutput + Reset + Default + Clone,
+    D::BlockSize: ArrayLength<u8>,
+{
+    let hmac = Hmac::<D>::new_varkey(secret).unwrap();
+
+    // A(1) = HMAC_hash(secret, seed)
+    let mut mac = hmac.clone();
+    for part in seed {
+        mac.input(part);
+    }
+    let mut a = mac.result().code();
+
+    let mut chunks = out.chunks_mut(D::OutputSize::to_usize()).peekable();
+    while let Some(chunk) = chunks.next() {
+        // HMAC_hash(secret, A(i) + seed)
+        let mut mac = hmac.clone();
+        mac.input(&a);
+        for part in seed {
+            mac.input(part);
+        }
+        let block = mac.result().code();
+        chunk.copy_from_slice(&block[..chunk.len()]);
+
+        // A(i + 1) = HMAC_hash(secret, A(i)), only needed for another chunk
+        if chunks.peek().is_some() {
+            let mut mac = hmac.clone();
+            mac.input(&a);
+            a = mac.result().code();
+        }
+    }
+}
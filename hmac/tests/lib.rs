@@ -1,17 +1,546 @@
 //! Test vectors from:
 //! - md5: RFC 2104, plus wiki test
 //! - sha2: RFC 4231
+//! - TLS 1.2 PRF: vectors published on the IETF TLS mailing list
 #![no_std]
 #[macro_use]
 extern crate crypto_mac;
 extern crate hmac;
 extern crate md5;
 extern crate sha2;
+#[macro_use]
+extern crate std;
 
-use hmac::Hmac;
+use crypto_mac::MacError;
+use hmac::{tls_prf, GenericArray, Hmac, LabeledHmac, Mac};
 
 new_test!(hmac_md5, "md5", Hmac<md5::Md5>);
 new_test!(hmac_sha224, "sha224", Hmac<sha2::Sha224>);
 new_test!(hmac_sha256, "sha256", Hmac<sha2::Sha256>);
 new_test!(hmac_sha384, "sha384", Hmac<sha2::Sha384>);
 new_test!(hmac_sha512, "sha512", Hmac<sha2::Sha512>);
+
+#[test]
+fn hmac_result_reset() {
+    let key = b"my secret and secure key";
+    let mut mac = Hmac::<sha2::Sha256>::new_varkey(key).unwrap();
+    for msg in [&b"first message"[..], &b"second message"[..]].iter() {
+        let mut fresh = Hmac::<sha2::Sha256>::new_varkey(key).unwrap();
+        fresh.input(msg);
+
+        mac.input(msg);
+        assert!(mac.result_reset() == fresh.result());
+    }
+}
+
+#[test]
+fn hmac_block_size() {
+    assert_eq!(Hmac::<md5::Md5>::block_size(), 64);
+    assert_eq!(Hmac::<sha2::Sha256>::block_size(), 64);
+    assert_eq!(Hmac::<sha2::Sha512>::block_size(), 128);
+    assert_eq!(Hmac::<md5::Md5>::BLOCK_SIZE, 64);
+    assert_eq!(Hmac::<sha2::Sha256>::BLOCK_SIZE, 64);
+    assert_eq!(Hmac::<sha2::Sha512>::BLOCK_SIZE, 128);
+}
+
+#[test]
+fn hmac_chain() {
+    let key = b"my secret and secure key";
+    let mut mac = Hmac::<sha2::Sha256>::new_varkey(key).unwrap();
+    mac.input(b"input message");
+    let expected = mac.result();
+
+    let chained = Hmac::<sha2::Sha256>::new_varkey(key)
+        .unwrap()
+        .chain(b"input ")
+        .chain(b"message")
+        .result();
+    assert!(expected == chained);
+}
+
+#[test]
+fn hmac_input_chunks() {
+    let expected = Hmac::<sha2::Sha256>::mac(b"key", b"input message").unwrap();
+
+    let mut mac = Hmac::<sha2::Sha256>::new_varkey(b"key").unwrap();
+    mac.input_chunks(
+        [&b"in"[..], &b""[..], &b"put mes"[..], &b"sage"[..]]
+            .iter()
+            .cloned(),
+    );
+    assert!(mac.result() == expected);
+}
+
+#[test]
+fn hmac_verify_slice() {
+    let mac = Hmac::<sha2::Sha256>::new_varkey(b"key")
+        .unwrap()
+        .chain(b"message");
+    let code = mac.clone().result().code();
+
+    assert!(mac.clone().verify(&code).is_ok());
+    assert_eq!(mac.clone().verify(&code[..31]), Err(MacError));
+    let mut wrong = code;
+    wrong[0] ^= 1;
+    assert_eq!(mac.verify(&wrong), Err(MacError));
+}
+
+#[test]
+fn hmac_verify_wrong_length() {
+    let mac = Hmac::<sha2::Sha256>::new_varkey(b"key")
+        .unwrap()
+        .chain(b"message");
+    let code = mac.clone().result().code();
+
+    // a correct prefix or a correct code followed by extra bytes must be
+    // rejected after the full fixed-size comparison
+    let mut long = [0u8; 33];
+    long[..32].copy_from_slice(&code);
+    assert_eq!(mac.clone().verify(&long), Err(MacError));
+    assert_eq!(mac.clone().verify(&code[..1]), Err(MacError));
+    assert_eq!(mac.verify(&[]), Err(MacError));
+}
+
+#[test]
+fn hmac_derive_nonce() {
+    let mac = Hmac::<sha2::Sha256>::new_varkey(b"key")
+        .unwrap()
+        .chain(b"message");
+    let code = mac.clone().result().code();
+
+    let mut nonce = [0u8; 12];
+    mac.clone().derive_nonce(&mut nonce).unwrap();
+    assert_eq!(&nonce[..], &code[..12]);
+
+    let mut nonce = [0u8; 32];
+    mac.clone().derive_nonce(&mut nonce).unwrap();
+    assert_eq!(&nonce[..], &code[..]);
+
+    assert!(mac.clone().derive_nonce(&mut [0u8; 33]).is_err());
+    assert!(mac.derive_nonce(&mut []).is_err());
+}
+
+#[cfg(feature = "subtle")]
+#[test]
+fn hmac_verify_ct_choice() {
+    let mac = Hmac::<sha2::Sha256>::new_varkey(b"key")
+        .unwrap()
+        .chain(b"message");
+    let code = mac.clone().result().code();
+
+    assert_eq!(mac.clone().verify_ct_choice(&code).unwrap_u8(), 1);
+    assert_eq!(mac.clone().verify_ct_choice(&code[..31]).unwrap_u8(), 0);
+    let mut wrong = code;
+    wrong[31] ^= 1;
+    assert_eq!(mac.verify_ct_choice(&wrong).unwrap_u8(), 0);
+}
+
+#[test]
+fn hmac_with_prefix() {
+    let key = b"key";
+    let expected = Hmac::<sha2::Sha256>::new_varkey(key)
+        .unwrap()
+        .chain(b"prefix message")
+        .result();
+
+    let mut mac = Hmac::<sha2::Sha256>::with_prefix(key, b"prefix ").unwrap();
+    mac.input(b"message");
+    assert!(mac.result_reset() == expected);
+
+    mac.input(b"garbage");
+    mac.reset();
+    mac.input(b"message");
+    assert!(mac.result() == expected);
+}
+
+#[test]
+fn hmac_labeled() {
+    let expected = Hmac::<sha2::Sha256>::mac(b"key", b"label\x00message").unwrap();
+
+    let mut mac = LabeledHmac::<sha2::Sha256>::new(b"key", b"label\x00").unwrap();
+    mac.input(b"message");
+    assert!(mac.result_reset() == expected);
+
+    mac.input(b"garbage");
+    mac.reset();
+    assert!(mac.chain(b"message").result() == expected);
+}
+
+#[test]
+fn hmac_labeled_prefix_collision() {
+    // labels which are prefixes of each other are not domain separated
+    let ab = LabeledHmac::<sha2::Sha256>::new(b"key", b"ab").unwrap();
+    let a = LabeledHmac::<sha2::Sha256>::new(b"key", b"a").unwrap();
+    assert!(ab.chain(b"c").result() == a.chain(b"bc").result());
+}
+
+#[test]
+fn hmac_prehashed_key() {
+    use sha2::Digest;
+
+    let key = [0xaa; 131];
+    let expected = Hmac::<sha2::Sha256>::new_varkey(&key)
+        .unwrap()
+        .chain(b"message")
+        .result();
+
+    let hashed_key = sha2::Sha256::digest(&key);
+    let mac = Hmac::<sha2::Sha256>::new_prehashed_key(&hashed_key).chain(b"message");
+    assert!(mac.result() == expected);
+}
+
+#[test]
+fn hmac_one_shot() {
+    let key = b"my secret and secure key";
+    let expected = Hmac::<sha2::Sha256>::new_varkey(key)
+        .unwrap()
+        .chain(b"input message")
+        .result();
+
+    let result = Hmac::<sha2::Sha256>::mac(key, b"input message").unwrap();
+    assert!(result == expected);
+
+    let code = expected.code();
+    assert!(Hmac::<sha2::Sha256>::verify_mac(key, b"input message", &code).is_ok());
+    assert_eq!(
+        Hmac::<sha2::Sha256>::verify_mac(key, b"other message", &code),
+        Err(MacError)
+    );
+}
+
+#[test]
+fn hmac_finalize_into_reset() {
+    let mut mac = Hmac::<sha2::Sha256>::new_varkey(b"key").unwrap();
+    let mut out = GenericArray::default();
+    for msg in [&b"first"[..], &b"second"[..]].iter() {
+        let expected = Hmac::<sha2::Sha256>::mac(b"key", msg).unwrap().code();
+        mac.input(msg);
+        mac.finalize_into_reset(&mut out);
+        assert_eq!(out, expected);
+    }
+}
+
+#[test]
+fn hmac_ratchet() {
+    let key = [0x42; 32];
+    let (chain_key, message_key) = Hmac::<sha2::Sha256>::ratchet(&key);
+
+    let mac = Hmac::<sha2::Sha256>::new_varkey(&key).unwrap();
+    assert_eq!(chain_key, mac.clone().chain(&[0x01]).result().code());
+    assert_eq!(message_key, mac.chain(&[0x02]).result().code());
+    assert!(chain_key != message_key);
+}
+
+#[test]
+fn hmac_debug_redacts_key() {
+    let mac = Hmac::<md5::Md5>::new_varkey(&[0xaa; 16]).unwrap();
+    let debug = format!("{:?}", mac);
+    assert_eq!(debug, "Hmac { ... }");
+}
+
+#[cfg(feature = "primitives")]
+#[test]
+fn hmac_primitives() {
+    use hmac::primitives::{HmacSha1, HmacSha256, HmacSha512};
+
+    // RFC 2202 and RFC 4231, test case 2
+    let key = b"Jefe";
+    let msg = b"what do ya want for nothing?";
+    let expected_sha1 = [
+        0xef, 0xfc, 0xdf, 0x6a, 0xe5, 0xeb, 0x2f, 0xa2, 0xd2, 0x74, 0x16, 0xd5, 0xf1, 0x84, 0xdf,
+        0x9c, 0x25, 0x9a, 0x7c, 0x79,
+    ];
+    let expected_sha256 = [
+        0x5b, 0xdc, 0xc1, 0x46, 0xbf, 0x60, 0x75, 0x4e, 0x6a, 0x04, 0x24, 0x26, 0x08, 0x95, 0x75,
+        0xc7, 0x5a, 0x00, 0x3f, 0x08, 0x9d, 0x27, 0x39, 0x83, 0x9d, 0xec, 0x58, 0xb9, 0x64, 0xec,
+        0x38, 0x43,
+    ];
+    let expected_sha512 = [
+        0x16, 0x4b, 0x7a, 0x7b, 0xfc, 0xf8, 0x19, 0xe2, 0xe3, 0x95, 0xfb, 0xe7, 0x3b, 0x56, 0xe0,
+        0xa3, 0x87, 0xbd, 0x64, 0x22, 0x2e, 0x83, 0x1f, 0xd6, 0x10, 0x27, 0x0c, 0xd7, 0xea, 0x25,
+        0x05, 0x54, 0x97, 0x58, 0xbf, 0x75, 0xc0, 0x5a, 0x99, 0x4a, 0x6d, 0x03, 0x4f, 0x65, 0xf8,
+        0xf0, 0xe6, 0xfd, 0xca, 0xea, 0xb1, 0xa3, 0x4d, 0x4a, 0x6b, 0x4b, 0x63, 0x6e, 0x07, 0x0a,
+        0x38, 0xbc, 0xe7, 0x37,
+    ];
+
+    assert!(HmacSha1::verify_mac(key, msg, &expected_sha1).is_ok());
+    assert!(HmacSha256::verify_mac(key, msg, &expected_sha256).is_ok());
+    assert!(HmacSha512::verify_mac(key, msg, &expected_sha512).is_ok());
+}
+
+#[test]
+fn tls12_prf_sha256() {
+    let secret = [
+        0x9b, 0xbe, 0x43, 0x6b, 0xa9, 0x40, 0xf0, 0x17, 0xb1, 0x76, 0x52, 0x84, 0x9a, 0x71, 0xdb,
+        0x35,
+    ];
+    let seed = [
+        0xa0, 0xba, 0x9f, 0x93, 0x6c, 0xda, 0x31, 0x18, 0x27, 0xa6, 0xf7, 0x96, 0xff, 0xd5, 0x19,
+        0x8c,
+    ];
+    let expected = [
+        0xe3, 0xf2, 0x29, 0xba, 0x72, 0x7b, 0xe1, 0x7b, 0x8d, 0x12, 0x26, 0x20, 0x55, 0x7c, 0xd4,
+        0x53, 0xc2, 0xaa, 0xb2, 0x1d, 0x07, 0xc3, 0xd4, 0x95, 0x32, 0x9b, 0x52, 0xd4, 0xe6, 0x1e,
+        0xdb, 0x5a, 0x6b, 0x30, 0x17, 0x91, 0xe9, 0x0d, 0x35, 0xc9, 0xc9, 0xa4, 0x6b, 0x4e, 0x14,
+        0xba, 0xf9, 0xaf, 0x0f, 0xa0, 0x22, 0xf7, 0x07, 0x7d, 0xef, 0x17, 0xab, 0xfd, 0x37, 0x97,
+        0xc0, 0x56, 0x4b, 0xab, 0x4f, 0xbc, 0x91, 0x66, 0x6e, 0x9d, 0xef, 0x9b, 0x97, 0xfc, 0xe3,
+        0x4f, 0x79, 0x67, 0x89, 0xba, 0xa4, 0x80, 0x82, 0xd1, 0x22, 0xee, 0x42, 0xc5, 0xa7, 0x2e,
+        0x5a, 0x51, 0x10, 0xff, 0xf7, 0x01, 0x87, 0x34, 0x7b, 0x66,
+    ];
+
+    let mut out = [0u8; 100];
+    tls_prf::tls12_prf::<sha2::Sha256>(&secret, b"test label", &seed, &mut out);
+    assert_eq!(&expected[..], &out[..]);
+}
+
+#[test]
+fn tls12_prf_sha384() {
+    let secret = [
+        0xb8, 0x0b, 0x73, 0x3d, 0x6c, 0xee, 0xfc, 0xdc, 0x71, 0x56, 0x6e, 0xa4, 0x8e, 0x55, 0x67,
+        0xdf,
+    ];
+    let seed = [
+        0xcd, 0x66, 0x5c, 0xf6, 0xa8, 0x44, 0x7d, 0xd6, 0xff, 0x8b, 0x27, 0x55, 0x5e, 0xdb, 0x74,
+        0x65,
+    ];
+    let expected = [
+        0x7b, 0x0c, 0x18, 0xe9, 0xce, 0xd4, 0x10, 0xed, 0x18, 0x04, 0xf2, 0xcf, 0xa3, 0x4a, 0x33,
+        0x6a, 0x1c, 0x14, 0xdf, 0xfb, 0x49, 0x00, 0xbb, 0x5f, 0xd7, 0x94, 0x21, 0x07, 0xe8, 0x1c,
+        0x83, 0xcd, 0xe9, 0xca, 0x0f, 0xaa, 0x60, 0xbe, 0x9f, 0xe3, 0x4f, 0x82, 0xb1, 0x23, 0x3c,
+        0x91, 0x46, 0xa0, 0xe5, 0x34, 0xcb, 0x40, 0x0f, 0xed, 0x27, 0x00, 0x88, 0x4f, 0x9d, 0xc2,
+        0x36, 0xf8, 0x0e, 0xdd, 0x8b, 0xfa, 0x96, 0x11, 0x44, 0xc9, 0xe8, 0xd7, 0x92, 0xec, 0xa7,
+        0x22, 0xa7, 0xb3, 0x2f, 0xc3, 0xd4, 0x16, 0xd4, 0x73, 0xeb, 0xc2, 0xc5, 0xfd, 0x4a, 0xbf,
+        0xda, 0xd0, 0x5d, 0x91, 0x84, 0x25, 0x9b, 0x5b, 0xf8, 0xcd, 0x4d, 0x90, 0xfa, 0x0d, 0x31,
+        0xe2, 0xde, 0xc4, 0x79, 0xe4, 0xf1, 0xa2, 0x60, 0x66, 0xf2, 0xee, 0xa9, 0xa6, 0x92, 0x36,
+        0xa3, 0xe5, 0x26, 0x55, 0xc9, 0xe9, 0xae, 0xe6, 0x91, 0xc8, 0xf3, 0xa2, 0x68, 0x54, 0x30,
+        0x8d, 0x5e, 0xaa, 0x3b, 0xe8, 0x5e, 0x09, 0x90, 0x70, 0x3d, 0x73, 0xe5, 0x6f,
+    ];
+
+    let mut out = [0u8; 148];
+    tls_prf::tls12_prf::<sha2::Sha384>(&secret, b"test label", &seed, &mut out);
+    assert_eq!(&expected[..], &out[..]);
+}
+
+/// KMAC sample vectors from
+/// <https://csrc.nist.gov/projects/cryptographic-standards-and-guidelines/example-values>
+#[cfg(feature = "kmac")]
+mod kmac {
+    use crypto_mac::MacError;
+    use hmac::kmac::{Kmac128, Kmac256};
+    use hmac::Mac;
+
+    const KEY: [u8; 32] = [
+        0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4a, 0x4b, 0x4c, 0x4d, 0x4e,
+        0x4f, 0x50, 0x51, 0x52, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x5b, 0x5c, 0x5d,
+        0x5e, 0x5f,
+    ];
+    const TAGGED: &[u8] = b"My Tagged Application";
+
+    fn large_input() -> [u8; 200] {
+        let mut data = [0u8; 200];
+        for (i, b) in data.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        data
+    }
+
+    #[test]
+    fn kmac128_sample1() {
+        let expected = [
+            0xe5, 0x78, 0x0b, 0x0d, 0x3e, 0xa6, 0xf7, 0xd3, 0xa4, 0x29, 0xc5, 0x70, 0x6a, 0xa4,
+            0x3a, 0x00, 0xfa, 0xdb, 0xd7, 0xd4, 0x96, 0x28, 0x83, 0x9e, 0x31, 0x87, 0x24, 0x3f,
+            0x45, 0x6e, 0xe1, 0x4e,
+        ];
+        let mut mac = Kmac128::new_customized(&KEY, &[]);
+        mac.input(&[0x00, 0x01, 0x02, 0x03]);
+        assert_eq!(&expected[..], &mac.result_reset().code()[..]);
+
+        mac.input(&[0x00, 0x01, 0x02, 0x03]);
+        assert!(mac.verify(&expected).is_ok());
+    }
+
+    #[test]
+    fn kmac128_sample2() {
+        let expected = [
+            0x3b, 0x1f, 0xba, 0x96, 0x3c, 0xd8, 0xb0, 0xb5, 0x9e, 0x8c, 0x1a, 0x6d, 0x71, 0x88,
+            0x8b, 0x71, 0x43, 0x65, 0x1a, 0xf8, 0xba, 0x0a, 0x70, 0x70, 0xc0, 0x97, 0x9e, 0x28,
+            0x11, 0x32, 0x4a, 0xa5,
+        ];
+        let mut mac = Kmac128::new_customized(&KEY, TAGGED);
+        mac.input(&[0x00, 0x01, 0x02, 0x03]);
+        assert_eq!(&expected[..], &mac.result_reset().code()[..]);
+
+        mac.input(&[0x00, 0x01, 0x02, 0x03]);
+        assert!(mac.verify(&expected).is_ok());
+    }
+
+    #[test]
+    fn kmac128_sample3() {
+        let expected = [
+            0x1f, 0x5b, 0x4e, 0x6c, 0xca, 0x02, 0x20, 0x9e, 0x0d, 0xcb, 0x5c, 0xa6, 0x35, 0xb8,
+            0x9a, 0x15, 0xe2, 0x71, 0xec, 0xc7, 0x60, 0x07, 0x1d, 0xfd, 0x80, 0x5f, 0xaa, 0x38,
+            0xf9, 0x72, 0x92, 0x30,
+        ];
+        let mut mac = Kmac128::new_customized(&KEY, TAGGED);
+        mac.input(&large_input());
+        assert_eq!(&expected[..], &mac.result_reset().code()[..]);
+
+        mac.input(&large_input());
+        assert!(mac.verify(&expected).is_ok());
+    }
+
+    #[test]
+    fn kmac256_sample4() {
+        let expected = [
+            0x20, 0xc5, 0x70, 0xc3, 0x13, 0x46, 0xf7, 0x03, 0xc9, 0xac, 0x36, 0xc6, 0x1c, 0x03,
+            0xcb, 0x64, 0xc3, 0x97, 0x0d, 0x0c, 0xfc, 0x78, 0x7e, 0x9b, 0x79, 0x59, 0x9d, 0x27,
+            0x3a, 0x68, 0xd2, 0xf7, 0xf6, 0x9d, 0x4c, 0xc3, 0xde, 0x9d, 0x10, 0x4a, 0x35, 0x16,
+            0x89, 0xf2, 0x7c, 0xf6, 0xf5, 0x95, 0x1f, 0x01, 0x03, 0xf3, 0x3f, 0x4f, 0x24, 0x87,
+            0x10, 0x24, 0xd9, 0xc2, 0x77, 0x73, 0xa8, 0xdd,
+        ];
+        let mut mac = Kmac256::new_customized(&KEY, TAGGED);
+        mac.input(&[0x00, 0x01, 0x02, 0x03]);
+        assert_eq!(&expected[..], &mac.result_reset().code()[..]);
+
+        mac.input(&[0x00, 0x01, 0x02, 0x03]);
+        assert!(mac.verify(&expected).is_ok());
+    }
+
+    #[test]
+    fn kmac256_sample5() {
+        let expected = [
+            0x75, 0x35, 0x8c, 0xf3, 0x9e, 0x41, 0x49, 0x4e, 0x94, 0x97, 0x07, 0x92, 0x7c, 0xee,
+            0x0a, 0xf2, 0x0a, 0x3f, 0xf5, 0x53, 0x90, 0x4c, 0x86, 0xb0, 0x8f, 0x21, 0xcc, 0x41,
+            0x4b, 0xcf, 0xd6, 0x91, 0x58, 0x9d, 0x27, 0xcf, 0x5e, 0x15, 0x36, 0x9c, 0xbb, 0xff,
+            0x8b, 0x9a, 0x4c, 0x2e, 0xb1, 0x78, 0x00, 0x85, 0x5d, 0x02, 0x35, 0xff, 0x63, 0x5d,
+            0xa8, 0x25, 0x33, 0xec, 0x6b, 0x75, 0x9b, 0x69,
+        ];
+        let mut mac = Kmac256::new_customized(&KEY, &[]);
+        mac.input(&large_input());
+        assert_eq!(&expected[..], &mac.result_reset().code()[..]);
+
+        mac.input(&large_input());
+        assert!(mac.verify(&expected).is_ok());
+    }
+
+    #[test]
+    fn kmac256_sample6() {
+        let expected = [
+            0xb5, 0x86, 0x18, 0xf7, 0x1f, 0x92, 0xe1, 0xd5, 0x6c, 0x1b, 0x8c, 0x55, 0xdd, 0xd7,
+            0xcd, 0x18, 0x8b, 0x97, 0xb4, 0xca, 0x4d, 0x99, 0x83, 0x1e, 0xb2, 0x69, 0x9a, 0x83,
+            0x7d, 0xa2, 0xe4, 0xd9, 0x70, 0xfb, 0xac, 0xfd, 0xe5, 0x00, 0x33, 0xae, 0xa5, 0x85,
+            0xf1, 0xa2, 0x70, 0x85, 0x10, 0xc3, 0x2d, 0x07, 0x88, 0x08, 0x01, 0xbd, 0x18, 0x28,
+            0x98, 0xfe, 0x47, 0x68, 0x76, 0xfc, 0x89, 0x65,
+        ];
+        let mut mac = Kmac256::new_customized(&KEY, TAGGED);
+        mac.input(&large_input());
+        assert_eq!(&expected[..], &mac.result_reset().code()[..]);
+
+        mac.input(&large_input());
+        assert!(mac.verify(&expected).is_ok());
+    }
+
+    #[test]
+    fn kmac_verify_wrong_length() {
+        let mut mac = Kmac128::new_customized(&KEY, TAGGED);
+        mac.input(&[0x00, 0x01, 0x02, 0x03]);
+        let code = mac.clone().result().code();
+        let mut long = [0u8; 33];
+        long[..32].copy_from_slice(&code);
+        assert_eq!(mac.clone().verify(&long), Err(MacError));
+        assert_eq!(mac.clone().verify(&code[..1]), Err(MacError));
+        assert_eq!(mac.verify(&[]), Err(MacError));
+
+        let mut mac = Kmac256::new_customized(&KEY, TAGGED);
+        mac.input(&[0x00, 0x01, 0x02, 0x03]);
+        let code = mac.clone().result().code();
+        let mut long = [0u8; 65];
+        long[..64].copy_from_slice(&code);
+        assert_eq!(mac.clone().verify(&long), Err(MacError));
+        assert_eq!(mac.clone().verify(&code[..1]), Err(MacError));
+        assert_eq!(mac.verify(&[]), Err(MacError));
+    }
+}
+
+/// dudect-style statistical timing test, see
+/// <https://eprint.iacr.org/2016/1123.pdf>. Enabled with the `ct` feature
+/// since it takes a while and should be run on a quiet machine.
+#[cfg(feature = "ct")]
+mod ct {
+    use hmac::{Hmac, Mac};
+    use std::time::Instant;
+
+    const SAMPLES: usize = 200_000;
+    /// Threshold of Welch's t statistic above which timing is considered to
+    /// depend on the input class
+    const THRESHOLD: f64 = 4.5;
+
+    #[derive(Clone, Copy, Default)]
+    struct Stats {
+        n: f64,
+        mean: f64,
+        m2: f64,
+    }
+
+    impl Stats {
+        // Welford's online algorithm
+        fn push(&mut self, x: f64) {
+            self.n += 1.0;
+            let delta = x - self.mean;
+            self.mean += delta / self.n;
+            self.m2 += delta * (x - self.mean);
+        }
+
+        fn var(&self) -> f64 {
+            self.m2 / (self.n - 1.0)
+        }
+    }
+
+    fn welch_t(a: &Stats, b: &Stats) -> f64 {
+        (a.mean - b.mean) / (a.var() / a.n + b.var() / b.n).sqrt()
+    }
+
+    /// Compare the timing of `verify` for the correct code against a code
+    /// which differs in its first byte and against a code of the wrong
+    /// length.
+    ///
+    /// Since `verify` consumes the instance, the timed region includes the
+    /// finalization of the HMAC, which is much slower than the comparison
+    /// itself and thus limits the sensitivity of this test.
+    #[test]
+    fn hmac_verify() {
+        let mac = Hmac::<::sha2::Sha256>::new_varkey(b"key")
+            .unwrap()
+            .chain(b"message");
+        let good = mac.clone().result().code();
+        let mut bad = good;
+        bad[0] ^= 0xff;
+        let short = &good[..31];
+
+        let mut stats = [Stats::default(); 3];
+        // xorshift64, to pick the class of each measurement in random order
+        let mut rng = 0x2545_f491_4f6c_dd1du64;
+        for _ in 0..SAMPLES {
+            rng ^= rng << 13;
+            rng ^= rng >> 7;
+            rng ^= rng << 17;
+            let class = (rng % 3) as usize;
+            let code = match class {
+                0 => &good[..],
+                1 => &bad[..],
+                _ => short,
+            };
+
+            let mac = mac.clone();
+            let start = Instant::now();
+            let _ = mac.verify(code);
+            let elapsed = start.elapsed();
+            stats[class].push(f64::from(elapsed.subsec_nanos()));
+        }
+
+        let t = welch_t(&stats[0], &stats[1]);
+        assert!(t.abs() < THRESHOLD, "wrong value: t = {}", t);
+        let t = welch_t(&stats[0], &stats[2]);
+        assert!(t.abs() < THRESHOLD, "wrong length: t = {}", t);
+    }
+}